@@ -0,0 +1,237 @@
+use crate::bus::Bus;
+use crate::cpu::{StatusFlags, CPU};
+
+const DEFAULT_LOAD_ADDRESS: u16 = 0x8000;
+
+pub struct CpuBuilder {
+    bus: Bus,
+    program: Vec<u8>,
+    load_address: u16,
+    reset_vector: Option<u16>,
+    register_a: u8,
+    register_x: u8,
+    register_y: u8,
+    processor_status: StatusFlags,
+    halt_on_brk: bool,
+    decimal_enabled: bool,
+}
+
+impl Default for CpuBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuBuilder {
+    pub fn new() -> Self {
+        CpuBuilder {
+            bus: Bus::new(),
+            program: Vec::new(),
+            load_address: DEFAULT_LOAD_ADDRESS,
+            reset_vector: None,
+            register_a: 0,
+            register_x: 0,
+            register_y: 0,
+            processor_status: StatusFlags::empty(),
+            halt_on_brk: false,
+            decimal_enabled: false,
+        }
+    }
+
+    pub fn bus(mut self, bus: Bus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    pub fn program(mut self, program: Vec<u8>) -> Self {
+        self.program = program;
+        self
+    }
+
+    pub fn load_at(mut self, addr: u16) -> Self {
+        self.load_address = addr;
+        self
+    }
+
+    // defaults to the load address when a program is given
+    pub fn reset_vector(mut self, addr: u16) -> Self {
+        self.reset_vector = Some(addr);
+        self
+    }
+
+    pub fn register_a(mut self, value: u8) -> Self {
+        self.register_a = value;
+        self
+    }
+
+    pub fn register_x(mut self, value: u8) -> Self {
+        self.register_x = value;
+        self
+    }
+
+    pub fn register_y(mut self, value: u8) -> Self {
+        self.register_y = value;
+        self
+    }
+
+    pub fn processor_status(mut self, status: StatusFlags) -> Self {
+        self.processor_status = status;
+        self
+    }
+
+    pub fn halt_on_brk(mut self, halt: bool) -> Self {
+        self.halt_on_brk = halt;
+        self
+    }
+
+    pub fn decimal_enabled(mut self, enabled: bool) -> Self {
+        self.decimal_enabled = enabled;
+        self
+    }
+
+    // writes the program, resets through the vector, then applies the initial registers
+    pub fn build(self) -> Result<CPU, String> {
+        let mut cpu = CPU::with_bus(self.bus);
+        cpu.halt_on_brk = self.halt_on_brk;
+        cpu.decimal_enabled = self.decimal_enabled;
+
+        cpu.load_at(self.load_address, &self.program)?;
+
+        let reset_vector = match self.reset_vector {
+            Some(addr) => Some(addr),
+            None if !self.program.is_empty() => Some(self.load_address),
+            None => None,
+        };
+        if let Some(addr) = reset_vector {
+            cpu.set_reset_vector(addr)?;
+        }
+
+        cpu.reset();
+        cpu.register_a = self.register_a;
+        cpu.register_x = self.register_x;
+        cpu.register_y = self.register_y;
+        cpu.processor_status = self.processor_status;
+
+        Ok(cpu)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cartridge::test::test_rom;
+    use crate::cpu::Memory;
+
+    #[test]
+    fn test_builder_starts_at_custom_reset_vector() {
+        let mut cpu = CpuBuilder::new()
+            .program(vec![0xa9, 0x42, 0x00])
+            .halt_on_brk(true)
+            .load_at(0xc000)
+            .reset_vector(0xc000)
+            .build()
+            .unwrap();
+
+        assert_eq!(cpu.program_counter, 0xc000);
+
+        cpu.execute().unwrap();
+        assert_eq!(cpu.register_a, 0x42);
+        assert_eq!(cpu.program_counter, 0xc003);
+    }
+
+    #[test]
+    fn test_builder_reset_vector_defaults_to_load_address() {
+        let cpu = CpuBuilder::new()
+            .program(vec![0xea, 0x00])
+            .load_at(0x0600)
+            .build()
+            .unwrap();
+
+        assert_eq!(cpu.program_counter, 0x0600);
+        assert_eq!(cpu.memory_read(0x0600), 0xea);
+    }
+
+    #[test]
+    fn test_builder_applies_initial_registers() {
+        let mut cpu = CpuBuilder::new()
+            .program(vec![0x69, 0x01, 0x00])
+            .register_a(0x10)
+            .register_x(0x20)
+            .register_y(0x30)
+            .processor_status(StatusFlags::CARRY)
+            .halt_on_brk(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(cpu.register_x, 0x20);
+        assert_eq!(cpu.register_y, 0x30);
+
+        cpu.execute().unwrap();
+        assert_eq!(cpu.register_a, 0x12);
+    }
+
+    #[test]
+    fn test_builder_takes_irq_vector_on_brk_by_default() {
+        let cpu = CpuBuilder::new().build().unwrap();
+
+        assert!(!cpu.halt_on_brk);
+        assert!(!cpu.decimal_enabled);
+    }
+
+    #[test]
+    fn test_builder_passes_through_construction_flags() {
+        let cpu = CpuBuilder::new()
+            .halt_on_brk(true)
+            .decimal_enabled(true)
+            .build()
+            .unwrap();
+
+        assert!(cpu.halt_on_brk);
+        assert!(cpu.decimal_enabled);
+    }
+
+    #[test]
+    fn test_builder_rejects_program_past_end_of_memory() {
+        let result = CpuBuilder::new()
+            .program(vec![0xea; 0x11])
+            .load_at(0xfff0)
+            .build();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_builder_rejects_program_over_cartridge_prg_rom() {
+        let result = CpuBuilder::new()
+            .bus(Bus::with_rom(test_rom(vec![0x00; 0x4000])))
+            .program(vec![0xea, 0x00])
+            .build();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_builder_runs_cartridge_through_its_own_reset_vector() {
+        let mut prg_rom = vec![0x00; 0x4000];
+        prg_rom[0x0000] = 0xa9;
+        prg_rom[0x0001] = 0x42;
+        prg_rom[0x3ffc] = 0x00;
+        prg_rom[0x3ffd] = 0x80;
+
+        let result = CpuBuilder::new()
+            .bus(Bus::with_rom(test_rom(prg_rom.clone())))
+            .reset_vector(0xc000)
+            .build();
+        assert!(result.is_err());
+
+        let mut cpu = CpuBuilder::new()
+            .bus(Bus::with_rom(test_rom(prg_rom)))
+            .halt_on_brk(true)
+            .build()
+            .unwrap();
+        assert_eq!(cpu.program_counter, 0x8000);
+
+        cpu.execute().unwrap();
+        assert_eq!(cpu.register_a, 0x42);
+    }
+}
@@ -0,0 +1,273 @@
+use std::cell::RefCell;
+use crate::cartridge::Rom;
+use crate::cpu::Memory;
+
+//  _______________ $10000  _______________
+// | PRG-ROM       |       |               |
+// | Upper Bank    |       |               |
+// |_ _ _ _ _ _ _ _| $C000 | PRG-ROM       |
+// | PRG-ROM       |       |               |
+// | Lower Bank    |       |               |
+// |_______________| $8000 |_______________|
+// | SRAM          |       | SRAM          |
+// |_______________| $6000 |_______________|
+// | Expansion ROM |       | Expansion ROM |
+// |_______________| $4020 |_______________|
+// | I/O Registers |       |               |
+// |_ _ _ _ _ _ _ _| $4000 |               |
+// | Mirrors       |       | I/O Registers |
+// | $2000-$2007   |       |               |
+// |_ _ _ _ _ _ _ _| $2008 |               |
+// | I/O Registers |       |               |
+// |_______________| $2000 |_______________|
+// | Mirrors       |       |               |
+// | $0000-$07FF   |       |               |
+// |_ _ _ _ _ _ _ _| $0800 |               |
+// | RAM           |       | RAM           |
+// |_ _ _ _ _ _ _ _| $0200 |               |
+// | Stack         |       |               |
+// |_ _ _ _ _ _ _ _| $0100 |               |
+// | Zero Page     |       |               |
+// |_______________| $0000 |_______________|
+
+const RAM: u16 = 0x0000;
+const RAM_MIRRORS_END: u16 = 0x1FFF;
+const PRG_ROM: u16 = 0x8000;
+const PRG_ROM_END: u16 = 0xFFFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+pub type MemoryAccessHook = Box<dyn FnMut(AccessKind, u16, u8)>;
+
+// puts the hook back when dropped, so a panic while it is detached doesn't lose it
+struct DetachedHook<'a> {
+    slot: &'a RefCell<Option<MemoryAccessHook>>,
+    hook: Option<MemoryAccessHook>,
+}
+
+impl Drop for DetachedHook<'_> {
+    fn drop(&mut self) {
+        *self.slot.borrow_mut() = self.hook.take();
+    }
+}
+
+pub struct Bus {
+    memory: [u8; 0x10000],
+    rom: Option<Rom>,
+    // reads only borrow the bus, so the hook needs interior mutability to be called
+    on_memory_access: RefCell<Option<MemoryAccessHook>>,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Bus {
+            memory: [0; 0x10000],
+            rom: None,
+            on_memory_access: RefCell::new(None),
+        }
+    }
+
+    pub fn with_rom(rom: Rom) -> Self {
+        Bus {
+            memory: [0; 0x10000],
+            rom: Some(rom),
+            on_memory_access: RefCell::new(None),
+        }
+    }
+
+    // called with the address and value of every read and write going through the bus
+    pub fn set_on_memory_access(&mut self, hook: Option<MemoryAccessHook>) {
+        self.on_memory_access = RefCell::new(hook);
+    }
+
+    // runs `f` with the hook detached, for debugger reads that shouldn't show up in the log
+    pub(crate) fn without_memory_access_hook<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _detached = DetachedHook {
+            slot: &self.on_memory_access,
+            hook: self.on_memory_access.borrow_mut().take(),
+        };
+        f()
+    }
+
+    fn notify(&self, kind: AccessKind, addr: u16, data: u8) {
+        if let Some(hook) = self.on_memory_access.borrow_mut().as_mut() {
+            hook(kind, addr, data);
+        }
+    }
+
+    // writes to cartridge PRG-ROM are dropped
+    pub(crate) fn is_read_only(&self, addr: u16) -> bool {
+        matches!((&self.rom, addr), (Some(_), PRG_ROM..=PRG_ROM_END))
+    }
+
+    // the whole 64KB backing array, including the $8000+ area a cartridge shadows
+    pub(crate) fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub(crate) fn restore_memory(&mut self, memory: &[u8]) {
+        self.memory.copy_from_slice(memory);
+    }
+
+    fn read_prg_rom(rom: &Rom, addr: u16) -> u8 {
+        let mut addr = (addr - PRG_ROM) as usize;
+        if rom.prg_rom.len() == 0x4000 && addr >= 0x4000 {
+            // 16KB carts show up twice: $C000-$FFFF mirrors $8000-$BFFF
+            addr %= 0x4000;
+        }
+        rom.prg_rom[addr]
+    }
+
+    fn mirror(addr: u16) -> u16 {
+        match addr {
+            // only 11 bits are wired up for the 2KB internal RAM
+            RAM..=RAM_MIRRORS_END => addr & 0b0000_0111_1111_1111,
+            _ => addr,
+        }
+    }
+}
+
+impl Memory for Bus {
+
+    fn memory_read(&self, addr: u16) -> u8 {
+        let data = match (&self.rom, addr) {
+            (Some(rom), PRG_ROM..=PRG_ROM_END) => Bus::read_prg_rom(rom, addr),
+            _ => self.memory[Bus::mirror(addr) as usize],
+        };
+        self.notify(AccessKind::Read, addr, data);
+        data
+    }
+
+    fn memory_write(&mut self, addr: u16, data: u8) {
+        self.notify(AccessKind::Write, addr, data);
+        if !self.is_read_only(addr) {
+            self.memory[Bus::mirror(addr) as usize] = data;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cartridge::test::test_rom;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_ram_is_mirrored_up_to_0x1fff() {
+        let mut bus = Bus::new();
+        bus.memory_write(0x0001, 0x42);
+
+        assert_eq!(bus.memory_read(0x0801), 0x42);
+        assert_eq!(bus.memory_read(0x1001), 0x42);
+        assert_eq!(bus.memory_read(0x1801), 0x42);
+    }
+
+    #[test]
+    fn test_write_to_mirror_lands_in_ram() {
+        let mut bus = Bus::new();
+        bus.memory_write(0x1fff, 0x24);
+
+        assert_eq!(bus.memory_read(0x07ff), 0x24);
+    }
+
+    #[test]
+    fn test_addresses_above_ram_mirrors_are_not_mirrored() {
+        let mut bus = Bus::new();
+        bus.memory_write(0x2000, 0x11);
+        bus.memory_write(0x8000, 0x22);
+
+        assert_eq!(bus.memory_read(0x0000), 0x00);
+        assert_eq!(bus.memory_read(0x2000), 0x11);
+        assert_eq!(bus.memory_read(0x8000), 0x22);
+    }
+
+    #[test]
+    fn test_address_space_is_full_0x10000_bytes() {
+        let mut bus = Bus::new();
+        bus.memory_write(0xffff, 0x99);
+        bus.memory_write(0xfffe, 0x88);
+
+        assert_eq!(bus.memory.len(), 0x10000);
+        assert_eq!(bus.memory_read(0xffff), 0x99);
+        assert_eq!(bus.memory_read_u16(0xfffe), 0x9988);
+    }
+
+    #[test]
+    fn test_16kb_prg_rom_is_mirrored_into_upper_bank() {
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[0x0000] = 0x11;
+        prg_rom[0x3fff] = 0x22;
+        let bus = Bus::with_rom(test_rom(prg_rom));
+
+        assert_eq!(bus.memory_read(0x8000), 0x11);
+        assert_eq!(bus.memory_read(0xc000), 0x11);
+        assert_eq!(bus.memory_read(0xbfff), 0x22);
+        assert_eq!(bus.memory_read(0xffff), 0x22);
+    }
+
+    #[test]
+    fn test_32kb_prg_rom_is_not_mirrored() {
+        let mut prg_rom = vec![0; 0x8000];
+        prg_rom[0x0000] = 0x11;
+        prg_rom[0x4000] = 0x33;
+        let bus = Bus::with_rom(test_rom(prg_rom));
+
+        assert_eq!(bus.memory_read(0x8000), 0x11);
+        assert_eq!(bus.memory_read(0xc000), 0x33);
+    }
+
+    #[test]
+    fn test_writes_to_prg_rom_are_ignored() {
+        let mut bus = Bus::with_rom(test_rom(vec![0x44; 0x4000]));
+        bus.memory_write(0x8000, 0x00);
+        bus.memory_write(0x0000, 0x55);
+
+        assert_eq!(bus.memory_read(0x8000), 0x44);
+        assert_eq!(bus.memory_read(0x0000), 0x55);
+    }
+
+    #[test]
+    fn test_memory_access_hook_sees_unmirrored_address() {
+        let accesses = Rc::new(RefCell::new(vec![]));
+        let log = Rc::clone(&accesses);
+        let mut bus = Bus::new();
+        bus.set_on_memory_access(Some(Box::new(move |kind, addr, data| {
+            log.borrow_mut().push((kind, addr, data))
+        })));
+
+        bus.memory_write(0x0801, 0x42);
+        bus.memory_read(0x1001);
+
+        assert_eq!(
+            *accesses.borrow(),
+            vec![(AccessKind::Write, 0x0801, 0x42), (AccessKind::Read, 0x1001, 0x42)]
+        );
+    }
+
+    #[test]
+    fn test_memory_access_hook_survives_panic_while_detached() {
+        let count = Rc::new(RefCell::new(0));
+        let counter = Rc::clone(&count);
+        let mut bus = Bus::new();
+        bus.set_on_memory_access(Some(Box::new(move |_, _, _| *counter.borrow_mut() += 1)));
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            bus.without_memory_access_hook(|| panic!("formatter failed"))
+        }));
+        assert!(result.is_err());
+
+        bus.memory_read(0x0000);
+        assert_eq!(*count.borrow(), 1);
+    }
+}
@@ -0,0 +1,209 @@
+const NES_TAG: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+const PRG_ROM_PAGE_SIZE: usize = 16384;
+const CHR_ROM_PAGE_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    Vertical,
+    Horizontal,
+    FourScreen,
+}
+
+#[derive(Debug)]
+pub struct Rom {
+    pub prg_rom: Vec<u8>,
+    pub chr_rom: Vec<u8>,
+    pub mapper: u8,
+    pub screen_mirroring: Mirroring,
+}
+
+impl Rom {
+    // https://www.nesdev.org/wiki/INES
+    pub fn from_ines(raw: &[u8]) -> Result<Rom, String> {
+        if raw.len() < HEADER_SIZE || raw[0..4] != NES_TAG {
+            return Err("File is not in iNES file format".to_string());
+        }
+
+        let mapper = (raw[7] & 0b1111_0000) | (raw[6] >> 4);
+
+        let ines_ver = (raw[7] >> 2) & 0b11;
+        if ines_ver != 0 {
+            return Err("NES2.0 format is not supported".to_string());
+        }
+
+        let four_screen = raw[6] & 0b1000 != 0;
+        let vertical_mirroring = raw[6] & 0b1 != 0;
+        let screen_mirroring = match (four_screen, vertical_mirroring) {
+            (true, _) => Mirroring::FourScreen,
+            (false, true) => Mirroring::Vertical,
+            (false, false) => Mirroring::Horizontal,
+        };
+
+        if raw[4] == 0 {
+            return Err("Header declares no PRG-ROM banks".to_string());
+        }
+
+        let prg_rom_size = raw[4] as usize * PRG_ROM_PAGE_SIZE;
+        let chr_rom_size = raw[5] as usize * CHR_ROM_PAGE_SIZE;
+
+        let skip_trainer = raw[6] & 0b100 != 0;
+
+        let prg_rom_start = HEADER_SIZE + if skip_trainer { TRAINER_SIZE } else { 0 };
+        let chr_rom_start = prg_rom_start + prg_rom_size;
+
+        if raw.len() < chr_rom_start + chr_rom_size {
+            return Err(format!(
+                "File is truncated: header declares {} bytes of PRG-ROM and {} bytes of CHR-ROM",
+                prg_rom_size, chr_rom_size
+            ));
+        }
+
+        Ok(Rom {
+            prg_rom: raw[prg_rom_start..chr_rom_start].to_vec(),
+            chr_rom: raw[chr_rom_start..(chr_rom_start + chr_rom_size)].to_vec(),
+            mapper,
+            screen_mirroring,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    pub struct TestRom {
+        pub header: Vec<u8>,
+        pub trainer: Option<Vec<u8>>,
+        pub prg_rom: Vec<u8>,
+        pub chr_rom: Vec<u8>,
+    }
+
+    pub fn create_rom(rom: TestRom) -> Vec<u8> {
+        let mut result = Vec::with_capacity(
+            rom.header.len()
+                + rom.trainer.as_ref().map_or(0, |t| t.len())
+                + rom.prg_rom.len()
+                + rom.chr_rom.len(),
+        );
+
+        result.extend(&rom.header);
+        if let Some(t) = rom.trainer {
+            result.extend(t);
+        }
+        result.extend(&rom.prg_rom);
+        result.extend(&rom.chr_rom);
+
+        result
+    }
+
+    pub fn test_rom(prg_rom: Vec<u8>) -> Rom {
+        let banks = (prg_rom.len() / PRG_ROM_PAGE_SIZE) as u8;
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x1A, banks, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: None,
+            prg_rom,
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        Rom::from_ines(&raw).unwrap()
+    }
+
+    #[test]
+    fn test_from_ines() {
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x1A, 0x02, 0x01, 0x31, 0x00, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: None,
+            prg_rom: vec![1; 2 * PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        let rom = Rom::from_ines(&raw).unwrap();
+
+        assert_eq!(rom.chr_rom, vec![2; CHR_ROM_PAGE_SIZE]);
+        assert_eq!(rom.prg_rom, vec![1; 2 * PRG_ROM_PAGE_SIZE]);
+        assert_eq!(rom.mapper, 3);
+        assert_eq!(rom.screen_mirroring, Mirroring::Vertical);
+    }
+
+    #[test]
+    fn test_from_ines_with_trainer() {
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x04, 0x10, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: Some(vec![0; TRAINER_SIZE]),
+            prg_rom: vec![1; PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        let rom = Rom::from_ines(&raw).unwrap();
+
+        assert_eq!(rom.prg_rom, vec![1; PRG_ROM_PAGE_SIZE]);
+        assert_eq!(rom.chr_rom, vec![2; CHR_ROM_PAGE_SIZE]);
+        assert_eq!(rom.mapper, 0x10);
+        assert_eq!(rom.screen_mirroring, Mirroring::Horizontal);
+    }
+
+    #[test]
+    fn test_from_ines_four_screen() {
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x1A, 0x01, 0x00, 0x09, 0x00, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: None,
+            prg_rom: vec![1; PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![],
+        });
+
+        let rom = Rom::from_ines(&raw).unwrap();
+
+        assert!(rom.chr_rom.is_empty());
+        assert_eq!(rom.screen_mirroring, Mirroring::FourScreen);
+    }
+
+    #[test]
+    fn test_rejects_missing_magic() {
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x00, 0x01, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: None,
+            prg_rom: vec![1; PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        assert!(Rom::from_ines(&raw).is_err());
+    }
+
+    #[test]
+    fn test_rejects_nes2() {
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x00, 0x08, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: None,
+            prg_rom: vec![1; PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        assert!(Rom::from_ines(&raw).is_err());
+    }
+
+    #[test]
+    fn test_rejects_truncated_file() {
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x1A, 0x02, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: None,
+            prg_rom: vec![1; PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![],
+        });
+
+        assert!(Rom::from_ines(&raw).is_err());
+    }
+
+    #[test]
+    fn test_rejects_zero_prg_banks() {
+        let raw = create_rom(TestRom {
+            header: vec![0x4E, 0x45, 0x53, 0x1A, 0x00, 0x00, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0],
+            trainer: None,
+            prg_rom: vec![],
+            chr_rom: vec![],
+        });
+
+        assert!(Rom::from_ines(&raw).is_err());
+    }
+}
@@ -1,5 +1,10 @@
+pub mod builder;
+pub mod bus;
+pub mod cartridge;
 pub mod cpu;
 pub mod opcodes;
+pub mod savestate;
+pub mod trace;
 
 #[macro_use]
 extern crate lazy_static;
\ No newline at end of file
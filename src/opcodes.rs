@@ -13,11 +13,11 @@ pub struct OpCode {
 impl OpCode {
     fn new(code: u8, mnemonic: &'static str, len: u8, cycles: u8, mode: AddressingMode) -> Self {
         OpCode {
-            code: code,
-            mnemonic: mnemonic,
-            len: len,
-            cycles: cycles,
-            mode: mode,
+            code,
+            mnemonic,
+            len,
+            cycles,
+            mode,
         }
     }
 }
@@ -125,7 +125,7 @@ lazy_static! {
         map.insert(0x21, OpCode::new(0x21, "AND", 2, 6, AddressingMode::Indirect_X));
         map.insert(0x31, OpCode::new(0x31, "AND", 2, 5/*+1 if page crossed*/, AddressingMode::Indirect_Y));
 
-        map.insert(0x0a, OpCode::new(0x0a, "ASL", 2, 2, AddressingMode::NoneAddressing));
+        map.insert(0x0a, OpCode::new(0x0a, "ASL", 1, 2, AddressingMode::NoneAddressing));
         map.insert(0x06, OpCode::new(0x06, "ASL", 2, 5, AddressingMode::ZeroPage));
         map.insert(0x16, OpCode::new(0x16, "ASL", 2, 6, AddressingMode::ZeroPage_X));
         map.insert(0x0e, OpCode::new(0x0e, "ASL", 3, 6, AddressingMode::Absolute));
@@ -173,7 +173,7 @@ lazy_static! {
         map.insert(0x3e, OpCode::new(0x3e, "ROL", 3, 7, AddressingMode::Absolute_X));
 
         map.insert(0x6a, OpCode::new(0x6a, "ROR", 1, 2, AddressingMode::NoneAddressing));
-        map.insert(0x26, OpCode::new(0x66, "ROR", 2, 5, AddressingMode::ZeroPage));
+        map.insert(0x66, OpCode::new(0x66, "ROR", 2, 5, AddressingMode::ZeroPage));
         map.insert(0x76, OpCode::new(0x76, "ROR", 2, 6, AddressingMode::ZeroPage_X));
         map.insert(0x6e, OpCode::new(0x6e, "ROR", 3, 6, AddressingMode::Absolute));
         map.insert(0x7e, OpCode::new(0x7e, "ROR", 3, 7, AddressingMode::Absolute_X));
@@ -207,6 +207,40 @@ lazy_static! {
         map.insert(0x08, OpCode::new(0x08, "PHP", 1, 3, AddressingMode::NoneAddressing));
         map.insert(0x28, OpCode::new(0x28, "PLP", 1, 4, AddressingMode::NoneAddressing));
 
+        //unofficial NOPs
+        map.insert(0x1a, OpCode::new(0x1a, "*NOP", 1, 2, AddressingMode::NoneAddressing));
+        map.insert(0x3a, OpCode::new(0x3a, "*NOP", 1, 2, AddressingMode::NoneAddressing));
+        map.insert(0x5a, OpCode::new(0x5a, "*NOP", 1, 2, AddressingMode::NoneAddressing));
+        map.insert(0x7a, OpCode::new(0x7a, "*NOP", 1, 2, AddressingMode::NoneAddressing));
+        map.insert(0xda, OpCode::new(0xda, "*NOP", 1, 2, AddressingMode::NoneAddressing));
+        map.insert(0xfa, OpCode::new(0xfa, "*NOP", 1, 2, AddressingMode::NoneAddressing));
+
+        map.insert(0x80, OpCode::new(0x80, "*NOP", 2, 2, AddressingMode::Immediate));
+        map.insert(0x82, OpCode::new(0x82, "*NOP", 2, 2, AddressingMode::Immediate));
+        map.insert(0x89, OpCode::new(0x89, "*NOP", 2, 2, AddressingMode::Immediate));
+        map.insert(0xc2, OpCode::new(0xc2, "*NOP", 2, 2, AddressingMode::Immediate));
+        map.insert(0xe2, OpCode::new(0xe2, "*NOP", 2, 2, AddressingMode::Immediate));
+
+        map.insert(0x04, OpCode::new(0x04, "*NOP", 2, 3, AddressingMode::ZeroPage));
+        map.insert(0x44, OpCode::new(0x44, "*NOP", 2, 3, AddressingMode::ZeroPage));
+        map.insert(0x64, OpCode::new(0x64, "*NOP", 2, 3, AddressingMode::ZeroPage));
+
+        map.insert(0x14, OpCode::new(0x14, "*NOP", 2, 4, AddressingMode::ZeroPage_X));
+        map.insert(0x34, OpCode::new(0x34, "*NOP", 2, 4, AddressingMode::ZeroPage_X));
+        map.insert(0x54, OpCode::new(0x54, "*NOP", 2, 4, AddressingMode::ZeroPage_X));
+        map.insert(0x74, OpCode::new(0x74, "*NOP", 2, 4, AddressingMode::ZeroPage_X));
+        map.insert(0xd4, OpCode::new(0xd4, "*NOP", 2, 4, AddressingMode::ZeroPage_X));
+        map.insert(0xf4, OpCode::new(0xf4, "*NOP", 2, 4, AddressingMode::ZeroPage_X));
+
+        map.insert(0x0c, OpCode::new(0x0c, "*NOP", 3, 4, AddressingMode::Absolute));
+
+        map.insert(0x1c, OpCode::new(0x1c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X));
+        map.insert(0x3c, OpCode::new(0x3c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X));
+        map.insert(0x5c, OpCode::new(0x5c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X));
+        map.insert(0x7c, OpCode::new(0x7c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X));
+        map.insert(0xdc, OpCode::new(0xdc, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X));
+        map.insert(0xfc, OpCode::new(0xfc, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X));
+
         map
     };
 
@@ -0,0 +1,151 @@
+use crate::cpu::{StatusFlags, CPU};
+
+// Layout (all multi-byte values little-endian):
+//   "NESS" magic, version byte,
+//   A, X, Y, P, SP, PC (2 bytes), cycles (8 bytes),
+//   the bus's full 0x10000-byte backing memory (with a cartridge mapped,
+//   the PRG-ROM range holds unused bytes rather than the ROM)
+const MAGIC: [u8; 4] = *b"NESS";
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = MAGIC.len() + 1;
+const REGISTERS_SIZE: usize = 5 + 2 + 8;
+const MEMORY_SIZE: usize = 0x10000;
+const STATE_SIZE: usize = HEADER_SIZE + REGISTERS_SIZE + MEMORY_SIZE;
+
+impl CPU {
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(STATE_SIZE);
+
+        data.extend(MAGIC);
+        data.push(VERSION);
+
+        data.push(self.register_a);
+        data.push(self.register_x);
+        data.push(self.register_y);
+        data.push(self.processor_status.bits());
+        data.push(self.stack_pointer);
+        data.extend(self.program_counter.to_le_bytes());
+        data.extend((self.cycles as u64).to_le_bytes());
+
+        data.extend(self.bus.memory());
+
+        data
+    }
+
+    // the CPU is left untouched unless the whole blob is valid
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+        if data.len() < HEADER_SIZE || data[0..4] != MAGIC {
+            return Err("Data is not a save state".to_string());
+        }
+
+        let version = data[4];
+        if version != VERSION {
+            return Err(format!("Save state version {} is not supported", version));
+        }
+
+        if data.len() != STATE_SIZE {
+            return Err(format!(
+                "Save state is {} bytes, expected {}",
+                data.len(),
+                STATE_SIZE
+            ));
+        }
+
+        let registers = &data[HEADER_SIZE..(HEADER_SIZE + REGISTERS_SIZE)];
+        self.register_a = registers[0];
+        self.register_x = registers[1];
+        self.register_y = registers[2];
+        self.processor_status = StatusFlags::from_bits(registers[3]);
+        self.stack_pointer = registers[4];
+        self.program_counter = u16::from_le_bytes([registers[5], registers[6]]);
+
+        let mut cycles = [0; 8];
+        cycles.copy_from_slice(&registers[7..15]);
+        self.cycles = u64::from_le_bytes(cycles) as usize;
+
+        self.bus.restore_memory(&data[(HEADER_SIZE + REGISTERS_SIZE)..]);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu::Memory;
+
+    fn run_sample_program() -> CPU {
+        let mut cpu = CPU::new();
+        // LDA #$42, STA $10, LDX #$05, PHA, SEC, BRK
+        cpu.load_and_run(vec![0xa9, 0x42, 0x85, 0x10, 0xa2, 0x05, 0x48, 0x38, 0x00]);
+        cpu
+    }
+
+    #[test]
+    fn test_save_and_restore_round_trip() {
+        let mut cpu = run_sample_program();
+        let state = cpu.save_state();
+
+        cpu.register_a = 0;
+        cpu.register_x = 0;
+        cpu.register_y = 0x99;
+        cpu.processor_status = StatusFlags::empty();
+        cpu.stack_pointer = 0x00;
+        cpu.program_counter = 0x1234;
+        cpu.cycles = 0;
+        cpu.memory_write(0x10, 0x00);
+        cpu.memory_write(0x01fd, 0x00);
+
+        cpu.load_state(&state).unwrap();
+
+        assert_eq!(cpu.register_a, 0x42);
+        assert_eq!(cpu.register_x, 0x05);
+        assert_eq!(cpu.register_y, 0x00);
+        assert!(cpu.processor_status.contains(StatusFlags::CARRY));
+        assert_eq!(cpu.stack_pointer, 0xfc);
+        assert_eq!(cpu.program_counter, 0x8009);
+        assert_eq!(cpu.cycles, 2 + 3 + 2 + 3 + 2 + 7);
+        assert_eq!(cpu.memory_read(0x10), 0x42);
+        assert_eq!(cpu.memory_read(0x01fd), 0x42);
+        assert_eq!(cpu.save_state(), state);
+    }
+
+    #[test]
+    fn test_state_has_magic_and_version() {
+        let state = run_sample_program().save_state();
+
+        assert_eq!(&state[0..4], b"NESS");
+        assert_eq!(state[4], VERSION);
+        assert_eq!(state.len(), STATE_SIZE);
+    }
+
+    #[test]
+    fn test_rejects_bad_magic() {
+        let mut state = run_sample_program().save_state();
+        state[0] = b'X';
+
+        let mut cpu = CPU::new();
+        assert!(cpu.load_state(&state).is_err());
+        assert_eq!(cpu.register_a, 0);
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let mut state = run_sample_program().save_state();
+        state[4] = VERSION + 1;
+
+        let mut cpu = CPU::new();
+        assert!(cpu.load_state(&state).is_err());
+        assert_eq!(cpu.register_a, 0);
+    }
+
+    #[test]
+    fn test_rejects_truncated_state() {
+        let state = run_sample_program().save_state();
+
+        let mut cpu = CPU::new();
+        assert!(cpu.load_state(&state[..state.len() - 1]).is_err());
+        assert!(cpu.load_state(&state[..3]).is_err());
+        assert_eq!(cpu.memory_read(0x10), 0);
+    }
+}
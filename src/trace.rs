@@ -0,0 +1,305 @@
+use crate::cpu::{AddressingMode, Memory, CPU};
+use crate::opcodes;
+
+impl CPU {
+    // formats the instruction at the program counter the way Nintendulator/nestest.log does:
+    // C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD
+    pub fn trace(&self) -> String {
+        // the disassembler's own reads would otherwise reach a memory access hook
+        self.bus.without_memory_access_hook(|| self.trace_line())
+    }
+
+    fn trace_line(&self) -> String {
+        let begin = self.program_counter;
+        let code = self.memory_read(begin);
+
+        let opcode = match opcodes::MAP.get(&code) {
+            Some(opcode) => opcode,
+            None => {
+                let asm_str = format!("{:04X}  {:02X}        ???", begin, code);
+                return self.with_registers(asm_str);
+            }
+        };
+
+        let mut hex_dump = vec![code];
+
+        let (mem_addr, stored_value) = match opcode.mode {
+            AddressingMode::Immediate | AddressingMode::NoneAddressing => (0, 0),
+            _ => {
+                let (addr, _) = self.get_absolute_address(&opcode.mode, begin.wrapping_add(1));
+                (addr, self.memory_read(addr))
+            }
+        };
+
+        let operand = match opcode.len {
+            1 => match opcode.code {
+                0x0a | 0x4a | 0x2a | 0x6a => "A".to_string(),
+                _ => String::from(""),
+            },
+            2 => {
+                let address = self.memory_read(begin.wrapping_add(1));
+                hex_dump.push(address);
+
+                match opcode.mode {
+                    AddressingMode::Immediate => format!("#${:02X}", address),
+                    AddressingMode::ZeroPage => format!("${:02X} = {:02X}", mem_addr, stored_value),
+                    AddressingMode::ZeroPage_X => format!(
+                        "${:02X},X @ {:02X} = {:02X}",
+                        address, mem_addr, stored_value
+                    ),
+                    AddressingMode::ZeroPage_Y => format!(
+                        "${:02X},Y @ {:02X} = {:02X}",
+                        address, mem_addr, stored_value
+                    ),
+                    AddressingMode::Indirect_X => format!(
+                        "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
+                        address,
+                        address.wrapping_add(self.register_x),
+                        mem_addr,
+                        stored_value
+                    ),
+                    AddressingMode::Indirect_Y => format!(
+                        "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
+                        address,
+                        mem_addr.wrapping_sub(self.register_y as u16),
+                        mem_addr,
+                        stored_value
+                    ),
+                    AddressingMode::NoneAddressing => {
+                        // branches: the offset is relative to the next instruction
+                        let target = begin
+                            .wrapping_add(2)
+                            .wrapping_add((address as i8) as u16);
+                        format!("${:04X}", target)
+                    }
+                    _ => panic!(
+                        "unexpected addressing mode {:?} for 2-byte opcode {:02X}",
+                        opcode.mode, opcode.code
+                    ),
+                }
+            }
+            3 => {
+                let address_lo = self.memory_read(begin.wrapping_add(1));
+                let address_hi = self.memory_read(begin.wrapping_add(2));
+                hex_dump.push(address_lo);
+                hex_dump.push(address_hi);
+
+                let address = self.memory_read_u16(begin.wrapping_add(1));
+
+                match opcode.mode {
+                    AddressingMode::NoneAddressing => {
+                        if opcode.code == 0x6c {
+                            // JMP indirect, including the page boundary bug
+                            let jmp_addr = if address & 0x00FF == 0x00FF {
+                                let lo = self.memory_read(address);
+                                let hi = self.memory_read(address & 0xFF00);
+                                (hi as u16) << 8 | (lo as u16)
+                            } else {
+                                self.memory_read_u16(address)
+                            };
+                            format!("(${:04X}) = {:04X}", address, jmp_addr)
+                        } else {
+                            format!("${:04X}", address)
+                        }
+                    }
+                    AddressingMode::Absolute => format!("${:04X} = {:02X}", mem_addr, stored_value),
+                    AddressingMode::Absolute_X => format!(
+                        "${:04X},X @ {:04X} = {:02X}",
+                        address, mem_addr, stored_value
+                    ),
+                    AddressingMode::Absolute_Y => format!(
+                        "${:04X},Y @ {:04X} = {:02X}",
+                        address, mem_addr, stored_value
+                    ),
+                    _ => panic!(
+                        "unexpected addressing mode {:?} for 3-byte opcode {:02X}",
+                        opcode.mode, opcode.code
+                    ),
+                }
+            }
+            _ => String::from(""),
+        };
+
+        let hex_str = hex_dump
+            .iter()
+            .map(|z| format!("{:02X}", z))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let asm_str = format!("{:04X}  {:8} {: >4} {}", begin, hex_str, opcode.mnemonic, operand)
+            .trim_end()
+            .to_string();
+
+        self.with_registers(asm_str)
+    }
+
+    fn with_registers(&self, asm_str: String) -> String {
+        format!(
+            "{:47} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+            asm_str,
+            self.register_a,
+            self.register_x,
+            self.register_y,
+            self.processor_status.bits(),
+            self.stack_pointer,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu::StatusFlags;
+
+    #[test]
+    fn test_format_matches_nestest_first_line() {
+        let mut cpu = CPU::new();
+        cpu.memory_write(0xc000, 0x4c);
+        cpu.memory_write(0xc001, 0xf5);
+        cpu.memory_write(0xc002, 0xc5);
+        cpu.program_counter = 0xc000;
+        cpu.processor_status = StatusFlags::from_bits(0x24);
+
+        assert_eq!(
+            "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD",
+            cpu.trace()
+        );
+    }
+
+    #[test]
+    fn test_format_trace() {
+        let mut cpu = CPU::new();
+        cpu.halt_on_brk = true;
+        cpu.memory_write(0x64, 0xa2);
+        cpu.memory_write(0x65, 0x01);
+        cpu.memory_write(0x66, 0xca);
+        cpu.memory_write(0x67, 0x88);
+        cpu.memory_write(0x68, 0x00);
+        cpu.program_counter = 0x64;
+        cpu.register_a = 1;
+        cpu.register_x = 2;
+        cpu.register_y = 3;
+
+        let mut result: Vec<String> = vec![];
+        cpu.run_with_callback(|cpu| {
+            result.push(cpu.trace());
+        })
+        .unwrap();
+
+        assert_eq!(
+            "0064  A2 01     LDX #$01                        A:01 X:02 Y:03 P:00 SP:FD",
+            result[0]
+        );
+        assert_eq!(
+            "0066  CA        DEX                             A:01 X:01 Y:03 P:00 SP:FD",
+            result[1]
+        );
+        assert_eq!(
+            "0067  88        DEY                             A:01 X:00 Y:03 P:02 SP:FD",
+            result[2]
+        );
+    }
+
+    #[test]
+    fn test_format_memory_access() {
+        let mut cpu = CPU::new();
+        cpu.halt_on_brk = true;
+        // ORA ($33),Y
+        cpu.memory_write(0x64, 0x11);
+        cpu.memory_write(0x65, 0x33);
+
+        //data
+        cpu.memory_write(0x33, 0x00);
+        cpu.memory_write(0x34, 0x04);
+
+        //target cell
+        cpu.memory_write(0x400, 0xAA);
+
+        cpu.program_counter = 0x64;
+        cpu.register_y = 0;
+
+        let mut result: Vec<String> = vec![];
+        cpu.run_with_callback(|cpu| {
+            result.push(cpu.trace());
+        })
+        .unwrap();
+
+        assert_eq!(
+            "0064  11 33     ORA ($33),Y = 0400 @ 0400 = AA  A:00 X:00 Y:00 P:00 SP:FD",
+            result[0]
+        );
+    }
+
+    #[test]
+    fn test_format_operand_modes() {
+        let mut cpu = CPU::new();
+        cpu.memory_write(0x0012, 0x34);
+        cpu.memory_write(0x0300, 0x56);
+        cpu.memory_write_u16(0x0020, 0x0300);
+
+        /*
+            LDX #$02
+            LDA $10,X
+            STA $0300
+            LDA $02FE,X
+            LDA ($1E,X)
+            ASL A
+            BEQ $8001
+            JMP ($0020)
+        */
+
+        cpu.load(vec![
+            0xa2, 0x02, 0xb5, 0x10, 0x8d, 0x00, 0x03, 0xbd, 0xfe, 0x02, 0xa1, 0x1e, 0x0a, 0xf0,
+            0xf2, 0x6c, 0x20, 0x00,
+        ])
+        .unwrap();
+        cpu.reset();
+
+        let mut result: Vec<String> = vec![];
+        for _ in 0..8 {
+            result.push(cpu.trace());
+            cpu.step().unwrap();
+        }
+
+        assert_eq!(
+            "8002  B5 10     LDA $10,X @ 12 = 34             A:00 X:02 Y:00 P:00 SP:FD",
+            result[1]
+        );
+        assert_eq!(
+            "8004  8D 00 03  STA $0300 = 56                  A:34 X:02 Y:00 P:00 SP:FD",
+            result[2]
+        );
+        assert_eq!(
+            "8007  BD FE 02  LDA $02FE,X @ 0300 = 34         A:34 X:02 Y:00 P:00 SP:FD",
+            result[3]
+        );
+        assert_eq!(
+            "800A  A1 1E     LDA ($1E,X) @ 20 = 0300 = 34    A:34 X:02 Y:00 P:00 SP:FD",
+            result[4]
+        );
+        assert_eq!(
+            "800C  0A        ASL A                           A:34 X:02 Y:00 P:00 SP:FD",
+            result[5]
+        );
+        assert_eq!(
+            "800D  F0 F2     BEQ $8001                       A:68 X:02 Y:00 P:00 SP:FD",
+            result[6]
+        );
+        assert_eq!(
+            "800F  6C 20 00  JMP ($0020) = 0300              A:68 X:02 Y:00 P:00 SP:FD",
+            result[7]
+        );
+    }
+
+    #[test]
+    fn test_format_unofficial_opcode() {
+        let mut cpu = CPU::new();
+        cpu.memory_write(0xc6bd, 0x04);
+        cpu.memory_write(0xc6be, 0xa9);
+        cpu.program_counter = 0xc6bd;
+
+        assert_eq!(
+            "C6BD  04 A9    *NOP $A9 = 00                    A:00 X:00 Y:00 P:00 SP:FD",
+            cpu.trace()
+        );
+    }
+}